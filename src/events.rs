@@ -0,0 +1,36 @@
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::serde::Serialize;
+use near_sdk::{log, AccountId};
+
+const STANDARD: &str = "divvy";
+const VERSION: &str = "1.0.0";
+
+// NEP-297 structured events for stash lifecycle actions, so indexers can follow
+// state changes off a log stream instead of replaying every transaction.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum DivvyEvent<'a> {
+  StashCreated { stash_id: u64, creator: &'a AccountId, name: &'a str },
+  VaultAdded { stash_id: u64, token_id: &'a AccountId },
+  LiquidityChanged { stash_id: u64, token_id: &'a AccountId, amount: Balance, new_share: Balance },
+  ContributorAuthorized { stash_id: u64, account_id: &'a AccountId },
+  StashRemoved { stash_id: u64 },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+  standard: &'static str,
+  version: &'static str,
+  #[serde(flatten)]
+  event: &'a DivvyEvent<'a>,
+}
+
+impl<'a> DivvyEvent<'a> {
+  pub fn emit(&self) {
+    let log_entry = EventLog { standard: STANDARD, version: VERSION, event: self };
+    log!("EVENT_JSON:{}", near_sdk::serde_json::to_string(&log_entry).unwrap());
+  }
+}
@@ -0,0 +1,37 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{ext_contract, AccountId, Gas, PromiseOrValue};
+
+// Mirrors the gas reservations used by the w-near core contract so callbacks
+// never run out of gas mid-resolution.
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas::from_tgas(25);
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas::from_tgas(5);
+pub const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas::from_tgas(30);
+pub const GAS_FOR_RESOLVE_SWAP: Gas = Gas::from_tgas(10);
+pub const GAS_FOR_MIGRATE: Gas = Gas::from_tgas(30);
+
+#[ext_contract(ext_ft)]
+pub trait ExtFungibleToken {
+  fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+  fn ft_transfer_call(
+    &mut self,
+    receiver_id: AccountId,
+    amount: U128,
+    memo: Option<String>,
+    msg: String,
+  ) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_dex)]
+pub trait ExtDex {
+  fn swap(&mut self, actions: Vec<SwapAction>) -> U128;
+}
+
+// The msg payload a DEX router expects on ft_transfer_call to execute a swap.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapAction {
+  pub token_in: AccountId,
+  pub token_out: AccountId,
+  pub min_amount_out: U128,
+}
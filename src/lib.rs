@@ -1,21 +1,48 @@
-use std::collections::HashMap;
-
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::fungible_token::Balance;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{UnorderedMap, UnorderedSet};
-use near_sdk::{env, near_bindgen, AccountId, NearToken, Promise, StorageUsage};
-
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, NearToken, Promise, PromiseOrValue, PromiseResult, StorageUsage};
+
+use crate::ext::{
+  ext_ft, SwapAction, GAS_FOR_FT_TRANSFER, GAS_FOR_FT_TRANSFER_CALL, GAS_FOR_MIGRATE,
+  GAS_FOR_RESOLVE_SWAP, GAS_FOR_RESOLVE_TRANSFER,
+};
+use crate::events::DivvyEvent;
+use crate::rbac::Role;
 use crate::stash::Stash;
+use crate::storage::{StorageBalance, StorageBalanceView};
 
 
+mod events;
+mod ext;
+mod rbac;
+mod storage;
 mod token_vault;
 mod stash;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Contract {
-  stashes: HashMap<u64, Stash>,
+  stashes: LookupMap<u64, Stash>,
+  next_stash_id: u64,
   accounts: UnorderedMap<AccountId, UnorderedSet<u64>>,
+  router_account_id: Option<AccountId>,
+  storage_balances: LookupMap<AccountId, StorageBalance>,
+  owner_id: AccountId,
+  stash_ids: UnorderedSet<u64>,
+}
+
+// pre-migration layout, kept around solely so `migrate` can read it back out of state
+#[derive(BorshDeserialize, BorshSerialize)]
+struct ContractV1 {
+  stashes: LookupMap<u64, Stash>,
+  next_stash_id: u64,
+  accounts: UnorderedMap<AccountId, UnorderedSet<u64>>,
+  router_account_id: Option<AccountId>,
+  storage_balances: LookupMap<AccountId, StorageBalance>,
+  owner_id: AccountId,
 }
 
 
@@ -26,64 +53,253 @@ impl Contract {
   pub fn new() -> Self {
     assert!(!env::state_exists(), "ERR_CONTRACT_IS_INITIALIZED");
     Self {
-      stashes: HashMap::new(),
+      stashes: LookupMap::new(b"t".to_vec()),
+      next_stash_id: 0,
       accounts: UnorderedMap::new(b"a".to_vec()),
+      router_account_id: None,
+      storage_balances: LookupMap::new(b"b".to_vec()),
+      owner_id: env::predecessor_account_id(),
+      stash_ids: UnorderedSet::new(b"i".to_vec()),
+    }
+  }
+
+  // ports state from the pre-upgrade layout (no stash_ids index) into the current one, so
+  // schema changes can roll out on a live deployment without stranding existing state.
+  // Older deploys never tracked stash ids directly, so they're recovered here from the
+  // per-account index (which is enumerable) rather than from `stashes`, a LookupMap that
+  // can't be walked. Once `stash_ids` exists, a future migrate() that changes Stash's or
+  // TokenVault's own layout can use it to walk and rewrite every entry in `stashes`.
+  #[private]
+  #[init(ignore_state)]
+  pub fn migrate() -> Self {
+    let old: ContractV1 = env::state_read().expect("ERR_NO_OLD_STATE");
+
+    let mut stash_ids: UnorderedSet<u64> = UnorderedSet::new(b"i".to_vec());
+    for (_, ids) in old.accounts.iter() {
+      for stash_id in ids.iter() {
+        stash_ids.insert(&stash_id);
+      }
+    }
+
+    Self {
+      stashes: old.stashes,
+      next_stash_id: old.next_stash_id,
+      accounts: old.accounts,
+      router_account_id: old.router_account_id,
+      storage_balances: old.storage_balances,
+      owner_id: old.owner_id,
+      stash_ids,
     }
   }
 
+  // owner-guarded code + state upgrade: deploys the wasm passed as input and chains a
+  // migrate() call in the same batch so the upgrade and migration land atomically
+  pub fn upgrade(&mut self) -> Promise {
+    assert_eq!(env::predecessor_account_id(), self.owner_id, "ERR_NOT_OWNER");
+    let code = env::input().expect("ERR_NO_INPUT");
+    Promise::new(env::current_account_id())
+      .deploy_contract(code)
+      .function_call("migrate".to_string(), Vec::new(), NearToken::from_yoctonear(0), GAS_FOR_MIGRATE)
+  }
+
+  pub fn set_router_account_id(&mut self, router_account_id: AccountId) {
+    assert_eq!(env::predecessor_account_id(), self.owner_id, "ERR_NOT_OWNER");
+    self.router_account_id = Some(router_account_id);
+  }
+
+  // NEP-145: pre-fund an account's storage balance, which is also where freed storage is
+  // credited back to. Calls that add storage still pay for it from their attached deposit;
+  // this balance is not drawn on to cover them.
+  #[payable]
+  pub fn storage_deposit(&mut self, account_id: Option<AccountId>) -> StorageBalanceView {
+    let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+    let mut balance = self.storage_balances.get(&account_id).unwrap_or_default();
+    balance.credit(env::attached_deposit().as_yoctonear());
+    self.storage_balances.insert(&account_id, &balance);
+    balance.to_view()
+  }
+
+  // NEP-145: withdraw up to `amount` (or everything available) of the caller's
+  // pre-funded, currently-unused storage balance
+  pub fn storage_withdraw(&mut self, amount: Option<NearToken>) -> StorageBalanceView {
+    let account_id = env::predecessor_account_id();
+    let mut balance = self.storage_balances.get(&account_id).expect("ERR_NO_STORAGE_BALANCE");
+    let amount = amount.map_or(balance.available, |amount| amount.as_yoctonear());
+    balance.debit(amount);
+    self.storage_balances.insert(&account_id, &balance);
+    if amount > 0 {
+      Promise::new(account_id).transfer(NearToken::from_yoctonear(amount));
+    }
+    balance.to_view()
+  }
+
+  pub fn storage_balance_of(&self, account_id: AccountId) -> StorageBalanceView {
+    self.storage_balances.get(&account_id).unwrap_or_default().to_view()
+  }
+
 
   #[payable]
   pub fn create_stash(&mut self, name: String) {
     let prev_storage = env::storage_usage();
-    let stash_id = self.stashes.len() as u64;
-    self.stashes.insert(stash_id, Stash::new(stash_id, name));
+    let stash_id = self.next_stash_id;
+    let creator = env::predecessor_account_id();
+    self.stashes.insert(&stash_id, &Stash::new(stash_id, name.clone(), creator.clone()));
+    self.next_stash_id += 1;
+    self.stash_ids.insert(&stash_id);
 
-    let mut set: UnorderedSet<u64> = self.accounts.get(&env::predecessor_account_id()).unwrap_or_else(|| UnorderedSet::new(b"s".to_vec()));
+    let mut set: UnorderedSet<u64> = self.accounts.get(&creator).unwrap_or_else(|| UnorderedSet::new(b"s".to_vec()));
     set.insert(&stash_id);
-    self.accounts.insert(&env::predecessor_account_id(), &set);
+    self.accounts.insert(&creator, &set);
 
     self.internal_check_storage(prev_storage);
 
+    DivvyEvent::StashCreated { stash_id, creator: &creator, name: &name }.emit();
   }
 
   // add tokenVault into a stash
   pub fn add_token_to_stash(&mut self, stash_id: u64, token_id: AccountId) {
     let prev_storage = env::storage_usage();
-    let stash = self.stashes.get_mut(&stash_id).expect("ERR_STASH_NOT_FOUND");
-    stash.add_vault(token_id);
+    self.internal_require_role(stash_id, &env::predecessor_account_id(), Role::Admin);
+    self.internal_with_stash(stash_id, |stash| stash.add_vault(token_id.clone()));
     self.internal_check_storage(prev_storage);
+    DivvyEvent::VaultAdded { stash_id, token_id: &token_id }.emit();
   }
 
-  // TODO swaps given amount_in of token_in into token_out
-  pub fn deposit_swap(&mut self, _stash_id:u64, _token_in: AccountId, _token_out: AccountId, _amount_in: Balance, _min_amount_out: Balance) {
+  // swaps amount_in of token_in held by the stash into token_out through the configured DEX
+  // router in a single ft_transfer_call, with the swap action encoded in `msg` so the router
+  // can correlate the incoming funds with what to do with them; the router's ft_on_transfer
+  // resolves to the realized amount_out, which resolve_swap then credits into the stash
+  pub fn deposit_swap(
+    &mut self,
+    stash_id: u64,
+    token_in: AccountId,
+    token_out: AccountId,
+    amount_in: Balance,
+    min_amount_out: Balance,
+  ) -> Promise {
+    let router_account_id = self.router_account_id.clone().expect("ERR_ROUTER_NOT_SET");
+    let account_id = env::predecessor_account_id();
+    self.internal_require_role(stash_id, &account_id, Role::Contributor);
+    self.internal_with_stash(stash_id, |stash| stash.withdraw(&token_in, &account_id, amount_in));
+
+    let msg = near_sdk::serde_json::to_string(&SwapAction {
+      token_in: token_in.clone(),
+      token_out: token_out.clone(),
+      min_amount_out: U128(min_amount_out),
+    })
+    .expect("ERR_SERIALIZE_SWAP_ACTION");
+
+    ext_ft::ext(token_in.clone())
+      .with_static_gas(GAS_FOR_FT_TRANSFER_CALL)
+      .with_attached_deposit(NearToken::from_yoctonear(1))
+      .ft_transfer_call(router_account_id, U128(amount_in), None, msg)
+      .then(
+        Self::ext(env::current_account_id())
+          .with_static_gas(GAS_FOR_RESOLVE_SWAP)
+          .resolve_swap(stash_id, token_in, token_out, account_id, amount_in),
+      )
+  }
 
-    // how to swap this via an agent and update stash.deposits
+  // callback for deposit_swap: credits the depositor's share of the stash's token_out vault
+  // with the realized amount_out the router's ft_on_transfer resolved to, or re-credits the
+  // withdrawer's token_in share if the transfer into the router failed outright
+  #[private]
+  pub fn resolve_swap(
+    &mut self,
+    stash_id: u64,
+    token_in: AccountId,
+    token_out: AccountId,
+    account_id: AccountId,
+    amount_in: Balance,
+  ) {
+    match env::promise_result(0) {
+      PromiseResult::Successful(value) => {
+        let amount_out = near_sdk::serde_json::from_slice::<U128>(&value).expect("ERR_INVALID_SWAP_RESULT").0;
+        self.internal_with_stash(stash_id, |stash| stash.deposit(token_out, account_id, amount_out));
+      }
+      _ => {
+        self.internal_with_stash(stash_id, |stash| stash.deposit(token_in, account_id, amount_in));
+      }
+    }
   }
 
-  // add liquidity to a given stash
+  // add liquidity to a given stash, crediting the caller's own share so it can later be
+  // withdrawn via remove_liquidity_from_stash's share-tracked debit
   pub fn add_liquidity_to_stash(&mut self, stash_id: u64, token_id: AccountId, amount: Balance) {
     let prev_storage = env::storage_usage();
-    let stash = self.stashes.get_mut(&stash_id).expect("ERR_STASH_NOT_FOUND");
-    stash.add_liquidity(token_id, amount);
+    let account_id = env::predecessor_account_id();
+    self.internal_require_role(stash_id, &account_id, Role::Contributor);
+    self.internal_with_stash(stash_id, |stash| stash.deposit(token_id.clone(), account_id.clone(), amount));
     self.internal_check_storage(prev_storage);
+    let new_share = self.internal_share_of(stash_id, &token_id, &account_id);
+    DivvyEvent::LiquidityChanged { stash_id, token_id: &token_id, amount, new_share }.emit();
   }
 
-  // remove liquidity from a given stash
-  pub fn remove_liquidity_from_stash(&mut self, stash_id: u64, token_id: AccountId, amount: Balance) {
+  // remove liquidity from a given stash, transferring the underlying tokens back to the caller
+  pub fn remove_liquidity_from_stash(&mut self, stash_id: u64, token_id: AccountId, amount: Balance) -> Promise {
     let prev_storage = env::storage_usage();
-    let stash = self.stashes.get_mut(&stash_id).expect("ERR_STASH_NOT_FOUND");
-    stash.remove_liquidity(token_id, amount);
+    let account_id = env::predecessor_account_id();
+    self.internal_require_role(stash_id, &account_id, Role::Contributor);
+    self.internal_with_stash(stash_id, |stash| stash.withdraw(&token_id, &account_id, amount));
     self.internal_check_storage(prev_storage);
+    let new_share = self.internal_share_of(stash_id, &token_id, &account_id);
+    DivvyEvent::LiquidityChanged { stash_id, token_id: &token_id, amount, new_share }.emit();
+
+    ext_ft::ext(token_id.clone())
+      .with_static_gas(GAS_FOR_FT_TRANSFER)
+      .with_attached_deposit(NearToken::from_yoctonear(1))
+      .ft_transfer(account_id.clone(), U128(amount), None)
+      .then(
+        Self::ext(env::current_account_id())
+          .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+          .resolve_withdraw(stash_id, token_id, account_id, amount),
+      )
+  }
+
+  // callback for remove_liquidity_from_stash: re-credits the withdrawer's share if the
+  // underlying ft_transfer failed instead of letting the tokens vanish
+  #[private]
+  pub fn resolve_withdraw(&mut self, stash_id: u64, token_id: AccountId, account_id: AccountId, amount: Balance) {
+    if !matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+      self.internal_with_stash(stash_id, |stash| stash.deposit(token_id, account_id, amount));
+    }
   }
 
   // authorize additional stash contributor
   pub fn authorize_contributor(&mut self, stash_id: u64, account_id: AccountId) {
     let prev_storage = env::storage_usage();
-    let stash = self.stashes.get_mut(&stash_id).expect("ERR_STASH_NOT_FOUND");
-    stash.authorize_contributor(account_id);
+    self.internal_require_role(stash_id, &env::predecessor_account_id(), Role::Admin);
+    self.internal_with_stash(stash_id, |stash| stash.authorize_contributor(account_id.clone()));
+    self.internal_check_storage(prev_storage);
+    DivvyEvent::ContributorAuthorized { stash_id, account_id: &account_id }.emit();
+  }
+
+  // grant a stash-scoped role to an account; the caller must already hold at least the role
+  // being granted, so e.g. an Admin can hand out Contributor/Admin but never Owner
+  #[payable]
+  pub fn grant_role(&mut self, stash_id: u64, account_id: AccountId, role: Role) {
+    let prev_storage = env::storage_usage();
+    self.internal_require_role(stash_id, &env::predecessor_account_id(), role);
+    self.internal_with_stash(stash_id, |stash| stash.grant_role(account_id, role));
+    self.internal_check_storage(prev_storage);
+  }
+
+  // revoke an account's stash-scoped role; the caller must already hold at least the target's
+  // current role, so e.g. an Admin can't strip an Owner's access
+  #[payable]
+  pub fn revoke_role(&mut self, stash_id: u64, account_id: AccountId) {
+    let prev_storage = env::storage_usage();
+    let caller = env::predecessor_account_id();
+    let target_role = self.stashes.get(&stash_id).expect("ERR_STASH_NOT_FOUND").role_of(&account_id).expect("ERR_NO_ROLE");
+    self.internal_require_role(stash_id, &caller, target_role);
+    self.internal_with_stash(stash_id, |stash| stash.revoke_role(&account_id));
     self.internal_check_storage(prev_storage);
   }
 
+  pub fn has_role(&self, stash_id: u64, account_id: AccountId, role: Role) -> bool {
+    self.stashes.get(&stash_id).expect("ERR_STASH_NOT_FOUND").has_role(&account_id, role)
+  }
+
   pub fn get_stashes_for_account(&self, account_id: AccountId) -> Vec<u64> {
     self.accounts.get(&account_id).unwrap_or_else(|| UnorderedSet::new(b"s".to_vec())).to_vec()
   }
@@ -93,33 +309,76 @@ impl Contract {
   #[payable]
   pub fn remove_stash(&mut self, stash_id: u64) {
     let prev_storage = env::storage_usage();
+    self.internal_require_role(stash_id, &env::predecessor_account_id(), Role::Admin);
     self.stashes.remove(&stash_id);
+    self.stash_ids.remove(&stash_id);
     self.internal_check_storage(prev_storage);
+    DivvyEvent::StashRemoved { stash_id }.emit();
   }
 
 }
 
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+  // NEP-141 transfer-receiver hook: the calling token contract has already credited
+  // `amount` to us, `msg` carries the target stash_id, and we credit the sender's
+  // share in that stash's vault for the token. Returns 0 to keep the full amount.
+  fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+    let token_id = env::predecessor_account_id();
+    let stash_id: u64 = msg.parse().expect("ERR_INVALID_MSG");
+    self.internal_require_role(stash_id, &sender_id, Role::Contributor);
+    self.internal_with_stash(stash_id, |stash| stash.deposit(token_id, sender_id, amount.0));
+    PromiseOrValue::Value(U128(0))
+  }
+}
+
 // internal methods
 impl Contract {
 
-  fn internal_check_storage(&self, prev_storage: StorageUsage) -> u128 {
-      let storage_cost = env::storage_usage()
-          .checked_sub(prev_storage)
-          .unwrap_or_default() as Balance
-          * env::storage_byte_cost().as_yoctonear();
-
-      let refund = env::attached_deposit()
-          .checked_sub(NearToken::from_yoctonear(storage_cost))
-          .expect(
-              format!(
-                  "ERR_STORAGE_DEPOSIT need {}, attatched {}",
-                  storage_cost, env::attached_deposit()
-              ).as_str()
-          );
-      if !refund.is_zero() {
-          Promise::new(env::predecessor_account_id()).transfer(refund);
+  fn internal_require_role(&self, stash_id: u64, account_id: &AccountId, role: Role) {
+    let stash = self.stashes.get(&stash_id).expect("ERR_STASH_NOT_FOUND");
+    assert!(stash.has_role(account_id, role), "ERR_NOT_AUTHORIZED");
+  }
+
+  fn internal_share_of(&self, stash_id: u64, token_id: &AccountId, account_id: &AccountId) -> Balance {
+    self.stashes.get(&stash_id).expect("ERR_STASH_NOT_FOUND").share_of(token_id, account_id)
+  }
+
+  // loads a stash out of the lazily-loaded LookupMap, lets the caller mutate it in place,
+  // then writes it back so only the touched stash is read/written
+  fn internal_with_stash<R>(&mut self, stash_id: u64, f: impl FnOnce(&mut Stash) -> R) -> R {
+    let mut stash = self.stashes.get(&stash_id).expect("ERR_STASH_NOT_FOUND");
+    let result = f(&mut stash);
+    self.stashes.insert(&stash_id, &stash);
+    result
+  }
+
+  fn internal_check_storage(&mut self, prev_storage: StorageUsage) -> u128 {
+      let current_storage = env::storage_usage();
+
+      if current_storage >= prev_storage {
+        let storage_cost = (current_storage - prev_storage) as Balance * env::storage_byte_cost().as_yoctonear();
+
+        let refund = env::attached_deposit()
+            .checked_sub(NearToken::from_yoctonear(storage_cost))
+            .expect(
+                format!(
+                    "ERR_STORAGE_DEPOSIT need {}, attatched {}",
+                    storage_cost, env::attached_deposit()
+                ).as_str()
+            );
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        storage_cost
+      } else {
+        let freed_cost = (prev_storage - current_storage) as Balance * env::storage_byte_cost().as_yoctonear();
+        let account_id = env::predecessor_account_id();
+        let mut balance = self.storage_balances.get(&account_id).unwrap_or_default();
+        balance.credit(freed_cost);
+        self.storage_balances.insert(&account_id, &balance);
+        freed_cost
       }
-      storage_cost
   }
 }
 
@@ -127,7 +386,9 @@ impl Contract {
 #[cfg(test)]
 mod tests {
 
-    use near_sdk::{test_utils::{accounts, VMContextBuilder}, NearToken, testing_env};
+    use std::collections::HashMap;
+
+    use near_sdk::{test_utils::{accounts, VMContextBuilder}, NearToken, PromiseResult, RuntimeFeesConfig, VMConfig, testing_env};
 
     use super::*;
 
@@ -142,7 +403,7 @@ mod tests {
       let context = get_context(accounts(0));
       testing_env!(context.build());
       let contract = Contract::new();
-      assert!(contract.stashes.is_empty());
+      assert_eq!(contract.next_stash_id, 0);
       assert!(contract.accounts.is_empty());
     }
 
@@ -152,7 +413,7 @@ mod tests {
       testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
       let mut contract = Contract::new();
       contract.create_stash("Roommates".to_string());
-      assert_eq!(contract.stashes.len(), 1);
+      assert!(contract.stashes.get(&0).is_some());
       assert_eq!(contract.accounts.len(), 1);
     }
 
@@ -166,5 +427,130 @@ mod tests {
       contract.remove_stash(stash_id);
       assert!(contract.stashes.get(&stash_id).is_none());
     }
+
+    #[test]
+    fn test_resolve_withdraw_recredits_on_failure() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      let token_id = accounts(1);
+      contract.add_token_to_stash(stash_id, token_id.clone());
+      contract.internal_with_stash(stash_id, |stash| stash.deposit(token_id.clone(), accounts(0), 100));
+      contract.internal_with_stash(stash_id, |stash| stash.withdraw(&token_id, &accounts(0), 100));
+
+      testing_env!(
+        get_context(accounts(0)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![PromiseResult::Failed],
+      );
+      contract.resolve_withdraw(stash_id, token_id.clone(), accounts(0), 100);
+
+      let balance = contract.internal_with_stash(stash_id, |stash| stash.vault_balance(&token_id));
+      assert_eq!(balance, 100);
+    }
+
+    #[test]
+    fn test_resolve_swap_recredits_token_in_on_failure() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      let token_in = accounts(1);
+      let token_out = accounts(2);
+      contract.add_token_to_stash(stash_id, token_in.clone());
+      contract.add_token_to_stash(stash_id, token_out.clone());
+      contract.internal_with_stash(stash_id, |stash| stash.deposit(token_in.clone(), accounts(0), 100));
+      contract.internal_with_stash(stash_id, |stash| stash.withdraw(&token_in, &accounts(0), 100));
+
+      testing_env!(
+        get_context(accounts(0)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![PromiseResult::Failed],
+      );
+      contract.resolve_swap(stash_id, token_in.clone(), token_out.clone(), accounts(0), 100);
+
+      let token_in_balance = contract.internal_with_stash(stash_id, |stash| stash.vault_balance(&token_in));
+      let token_out_balance = contract.internal_with_stash(stash_id, |stash| stash.vault_balance(&token_out));
+      assert_eq!(token_in_balance, 100);
+      assert_eq!(token_out_balance, 0);
+    }
+
+    #[test]
+    fn test_resolve_swap_credits_depositor_share_on_success() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      let token_in = accounts(1);
+      let token_out = accounts(2);
+      contract.add_token_to_stash(stash_id, token_in.clone());
+      contract.add_token_to_stash(stash_id, token_out.clone());
+      contract.internal_with_stash(stash_id, |stash| stash.deposit(token_in.clone(), accounts(0), 100));
+      contract.internal_with_stash(stash_id, |stash| stash.withdraw(&token_in, &accounts(0), 100));
+
+      testing_env!(
+        get_context(accounts(0)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128(80)).unwrap())],
+      );
+      contract.resolve_swap(stash_id, token_in.clone(), token_out.clone(), accounts(0), 100);
+
+      let share = contract.internal_with_stash(stash_id, |stash| stash.share_of(&token_out, &accounts(0)));
+      assert_eq!(share, 80);
+    }
+
+    #[test]
+    fn test_add_liquidity_credits_callers_share() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      let token_id = accounts(1);
+      contract.add_token_to_stash(stash_id, token_id.clone());
+      contract.add_liquidity_to_stash(stash_id, token_id.clone(), 100);
+
+      let share = contract.internal_with_stash(stash_id, |stash| stash.share_of(&token_id, &accounts(0)));
+      assert_eq!(share, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_AUTHORIZED")]
+    fn test_add_liquidity_denies_non_contributor() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      let token_id = accounts(1);
+      contract.add_token_to_stash(stash_id, token_id.clone());
+
+      testing_env!(get_context(accounts(1)).build());
+      contract.add_liquidity_to_stash(stash_id, token_id, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_AUTHORIZED")]
+    fn test_grant_role_denies_self_promotion_above_caller_role() {
+      let mut context = get_context(accounts(0));
+      testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+      let mut contract = Contract::new();
+      contract.create_stash("Roommates".to_string());
+      let stash_id = 0;
+      contract.grant_role(stash_id, accounts(1), Role::Admin);
+
+      testing_env!(get_context(accounts(1)).build());
+      contract.grant_role(stash_id, accounts(1), Role::Owner);
+    }
 }
 
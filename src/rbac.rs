@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+// Stash-scoped roles, ordered so a higher variant satisfies a lower one's requirement.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+  Contributor,
+  Admin,
+  Owner,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Roles {
+  grants: HashMap<AccountId, Role>,
+}
+
+impl Roles {
+  pub fn grant(&mut self, account_id: AccountId, role: Role) {
+    self.grants.insert(account_id, role);
+  }
+
+  pub fn revoke(&mut self, account_id: &AccountId) {
+    self.grants.remove(account_id);
+  }
+
+  pub fn role_of(&self, account_id: &AccountId) -> Option<Role> {
+    self.grants.get(account_id).copied()
+  }
+
+  pub fn has_at_least(&self, account_id: &AccountId, role: Role) -> bool {
+    self.role_of(account_id).is_some_and(|granted| granted >= role)
+  }
+}
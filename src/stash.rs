@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::AccountId;
+
+use crate::rbac::{Role, Roles};
+use crate::token_vault::TokenVault;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Stash {
+  pub id: u64,
+  pub name: String,
+  pub vaults: HashMap<AccountId, TokenVault>,
+  pub roles: Roles,
+}
+
+impl Stash {
+  pub fn new(id: u64, name: String, creator: AccountId) -> Self {
+    let mut roles = Roles::default();
+    roles.grant(creator, Role::Owner);
+    Self {
+      id,
+      name,
+      vaults: HashMap::new(),
+      roles,
+    }
+  }
+
+  pub fn has_role(&self, account_id: &AccountId, role: Role) -> bool {
+    self.roles.has_at_least(account_id, role)
+  }
+
+  pub fn role_of(&self, account_id: &AccountId) -> Option<Role> {
+    self.roles.role_of(account_id)
+  }
+
+  pub fn vault_balance(&self, token_id: &AccountId) -> Balance {
+    self.vaults.get(token_id).map_or(0, |vault| vault.balance)
+  }
+
+  pub fn share_of(&self, token_id: &AccountId, account_id: &AccountId) -> Balance {
+    self.vaults.get(token_id).map_or(0, |vault| vault.share_of(account_id))
+  }
+
+  pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+    self.roles.grant(account_id, role);
+  }
+
+  pub fn revoke_role(&mut self, account_id: &AccountId) {
+    self.roles.revoke(account_id);
+  }
+
+  pub fn add_vault(&mut self, token_id: AccountId) {
+    self.vaults.entry(token_id.clone()).or_insert_with(|| TokenVault::new(token_id));
+  }
+
+  // credit a real NEP-141 deposit to the depositor's share of the named vault
+  pub fn deposit(&mut self, token_id: AccountId, account_id: AccountId, amount: Balance) {
+    let vault = self.vaults.get_mut(&token_id).expect("ERR_VAULT_NOT_FOUND");
+    vault.credit_share(account_id, amount);
+  }
+
+  // debit a depositor's share ahead of a real NEP-141 withdrawal
+  pub fn withdraw(&mut self, token_id: &AccountId, account_id: &AccountId, amount: Balance) {
+    let vault = self.vaults.get_mut(token_id).expect("ERR_VAULT_NOT_FOUND");
+    vault.debit_share(account_id, amount);
+  }
+
+  pub fn authorize_contributor(&mut self, account_id: AccountId) {
+    if self.roles.role_of(&account_id).is_none() {
+      self.roles.grant(account_id, Role::Contributor);
+    }
+  }
+}
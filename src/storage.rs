@@ -0,0 +1,34 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+
+// an account's pre-funded storage balance, tracked in yoctoNEAR
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
+pub struct StorageBalance {
+  pub total: u128,
+  pub available: u128,
+}
+
+impl StorageBalance {
+  pub fn credit(&mut self, amount: u128) {
+    self.total += amount;
+    self.available += amount;
+  }
+
+  pub fn debit(&mut self, amount: u128) {
+    self.available = self.available.checked_sub(amount).expect("ERR_INSUFFICIENT_STORAGE_BALANCE");
+    self.total -= amount;
+  }
+
+  pub fn to_view(&self) -> StorageBalanceView {
+    StorageBalanceView { total: U128(self.total), available: U128(self.available) }
+  }
+}
+
+// NEP-145-shaped view of a StorageBalance
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceView {
+  pub total: U128,
+  pub available: U128,
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use near_contract_standards::fungible_token::Balance;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::AccountId;
+
+// A single token's liquidity pool within a stash.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenVault {
+  pub token_id: AccountId,
+  pub balance: Balance,
+  pub shares: HashMap<AccountId, Balance>,
+}
+
+impl TokenVault {
+  pub fn new(token_id: AccountId) -> Self {
+    Self { token_id, balance: 0, shares: HashMap::new() }
+  }
+
+  pub fn deposit(&mut self, amount: Balance) {
+    self.balance += amount;
+  }
+
+  pub fn withdraw(&mut self, amount: Balance) {
+    self.balance = self.balance.checked_sub(amount).expect("ERR_INSUFFICIENT_BALANCE");
+  }
+
+  // credit amount to an account's share of this vault, e.g. after a real NEP-141 deposit
+  pub fn credit_share(&mut self, account_id: AccountId, amount: Balance) {
+    let share = self.shares.entry(account_id).or_insert(0);
+    *share += amount;
+    self.deposit(amount);
+  }
+
+  // debit amount from an account's share of this vault, e.g. ahead of a real NEP-141 withdrawal
+  pub fn debit_share(&mut self, account_id: &AccountId, amount: Balance) {
+    let share = self.shares.get_mut(account_id).expect("ERR_NO_SHARE");
+    *share = share.checked_sub(amount).expect("ERR_INSUFFICIENT_SHARE");
+    self.withdraw(amount);
+  }
+
+  pub fn share_of(&self, account_id: &AccountId) -> Balance {
+    self.shares.get(account_id).copied().unwrap_or(0)
+  }
+}